@@ -0,0 +1,20 @@
+#version 150
+
+in vec3 Color;
+in vec2 Texcoord;
+
+out vec4 out_color;
+
+uniform sampler2D tex_kitten;
+uniform sampler2D tex_puppy;
+uniform float time;
+uniform float exposure;
+
+void main() {
+    float mix_factor = (sin(time * 3.0) + 1.0) / 2.0;
+    vec4 col_kitten = texture(tex_kitten, Texcoord);
+    vec4 col_puppy = texture(tex_puppy, Texcoord);
+    vec4 mixed_texture = mix(col_kitten, col_puppy, mix_factor);
+    vec4 color = mix(vec4(Color, 1.0), mixed_texture, 0.25);
+    out_color = vec4(color.rgb * exposure, color.a);
+}
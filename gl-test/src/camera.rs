@@ -0,0 +1,98 @@
+use glfw::Key;
+use math::{Mat4, Vec3, TAU};
+
+/// How far the camera moves per second while a movement key is held.
+const MOVE_SPEED: f32 = 1.5;
+
+/// How far the camera turns (in radians) per pixel of mouse movement.
+const MOUSE_SENSITIVITY: f32 = 0.002;
+
+/// The furthest the camera can look up or down. Kept just short of straight up or down, where
+/// the view direction would be parallel to the up vector and `look_at` would break down.
+const MAX_PITCH: f32 = TAU / 4.0 - 0.01;
+
+/// The vertical direction of the world.
+const UP: Vec3 = Vec3([0.0, 0.0, 1.0]);
+
+/// A free-look camera, moved with WASD and turned with the mouse.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+
+    /// The horizontal angle of the view direction (in radians), counterclockwise from the X-axis.
+    pub yaw: f32,
+
+    /// The vertical angle of the view direction (in radians), positive above the horizon.
+    pub pitch: f32,
+}
+
+impl Camera {
+    /// Create a camera at `position` facing toward `target`.
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        let dir = (target - position).normalized();
+
+        Camera {
+            position: position,
+            yaw: dir[1].atan2(dir[0]),
+            pitch: dir[2].asin().max(-MAX_PITCH).min(MAX_PITCH),
+        }
+    }
+
+    /// The unit vector pointing in the direction the camera faces.
+    pub fn front(&self) -> Vec3 {
+        Vec3([
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+        ])
+    }
+
+    /// Build the view matrix for the camera's current position and orientation.
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at(self.position, self.position + self.front(), UP)
+    }
+
+    /// Move the camera for a movement key held down for `dt` seconds. Other keys are ignored.
+    pub fn process_keyboard(&mut self, key: Key, dt: f32) {
+        let front = self.front();
+        let right = front.cross(UP).normalized();
+        let distance = MOVE_SPEED * dt;
+
+        match key {
+            Key::W => self.position = self.position + front * distance,
+            Key::S => self.position = self.position - front * distance,
+            Key::A => self.position = self.position - right * distance,
+            Key::D => self.position = self.position + right * distance,
+            _ => {},
+        }
+    }
+
+    /// Turn the camera for a mouse movement of `dx` and `dy` pixels, in screen coordinates.
+    pub fn process_mouse(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * MOUSE_SENSITIVITY;
+        self.pitch -= dy * MOUSE_SENSITIVITY;
+        self.pitch = self.pitch.max(-MAX_PITCH).min(MAX_PITCH);
+    }
+}
+
+#[test]
+fn test_view_matrix() {
+    let eye = Vec3([1.2, 1.2, 1.2]);
+    let camera = Camera::looking_at(eye, Vec3::zero());
+    let expected = Mat4::look_at(eye, Vec3::zero(), UP);
+    assert!(camera.view_matrix().approx_eq(expected, 1e-5));
+}
+
+#[test]
+fn test_pitch_clamp() {
+    let mut camera = Camera::looking_at(Vec3::zero(), Vec3([1.0, 0.0, 0.0]));
+
+    // Looking far past straight up must stop just short of it rather than flipping over.
+    camera.process_mouse(0.0, -10000.0);
+    assert_eq!(MAX_PITCH, camera.pitch);
+    assert!(camera.front()[0] > 0.0);
+
+    camera.process_mouse(0.0, 20000.0);
+    assert_eq!(-MAX_PITCH, camera.pitch);
+    assert!(camera.front()[0] > 0.0);
+}
@@ -0,0 +1,79 @@
+//! Owning wrappers for OpenGL object handles which delete the object when dropped.
+//!
+//! OpenGL objects belong to the context they were created in, so these wrappers must be dropped
+//! while that context is still alive and current. In practice this means declaring them after the
+//! window, so they are dropped first.
+
+use gl;
+use gl::types::*;
+
+macro_rules! define_gl_object {
+    ($name:ident, $gen:path, $delete:path) => (
+        pub struct $name(GLuint);
+
+        impl $name {
+            /// Create a new object. The context it belongs to must be current.
+            pub unsafe fn new() -> Self {
+                let mut id = 0;
+                $gen(1, &mut id);
+                $name(id)
+            }
+
+            /// The raw handle of the object, for passing to OpenGL functions.
+            pub fn id(&self) -> GLuint {
+                self.0
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                unsafe { $delete(1, &self.0) };
+            }
+        }
+    );
+}
+
+define_gl_object!(Buffer, gl::GenBuffers, gl::DeleteBuffers);
+define_gl_object!(VertexArray, gl::GenVertexArrays, gl::DeleteVertexArrays);
+define_gl_object!(Texture, gl::GenTextures, gl::DeleteTextures);
+
+pub struct Shader(GLuint);
+
+impl Shader {
+    /// Create a new shader object of the given type (e.g. `gl::VERTEX_SHADER`). The context it
+    /// belongs to must be current.
+    pub unsafe fn new(shader_type: GLenum) -> Self {
+        Shader(gl::CreateShader(shader_type))
+    }
+
+    /// The raw handle of the shader, for passing to OpenGL functions.
+    pub fn id(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteShader(self.0) };
+    }
+}
+
+pub struct ShaderProgram(GLuint);
+
+impl ShaderProgram {
+    /// Create a new, empty program object. The context it belongs to must be current.
+    pub unsafe fn new() -> Self {
+        ShaderProgram(gl::CreateProgram())
+    }
+
+    /// The raw handle of the program, for passing to OpenGL functions.
+    pub fn id(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.0) };
+    }
+}
@@ -3,279 +3,150 @@ extern crate glfw;
 extern crate imagefmt;
 extern crate time;
 
-mod math;
-
-use gl::types::*;
-use glfw::{Context, OpenGlProfileHint, WindowHint, WindowMode};
-use std::mem;
-use std::ptr;
-
 macro_rules! gl_str {
     ($string_literal:expr) => (
         concat!($string_literal, '\0').as_bytes().as_ptr() as *const GLchar
     )
 }
 
-const VERTEX_SHADER_SOURCE: &'static str = "
-    #version 150
-
-    in vec2 position;
-    in vec3 color;
-    in vec2 texcoord;
-
-    out vec3 Color;
-    out vec2 Texcoord;
-
-    uniform mat4 trans;
-
-    void main() {
-        Color = color;
-        Texcoord = texcoord;
-        gl_Position = trans * vec4(position, 0.0, 1.0);
-    }
-";
-
-const FRAGMENT_SHADER_SOURCE: &'static str = "
-    #version 150
-
-    in vec3 Color;
-    in vec2 Texcoord;
-
-    out vec4 out_color;
-
-    uniform sampler2D tex_kitten;
-    uniform sampler2D tex_puppy;
-    uniform float time;
-
-    void main() {
-        float mix_factor = (sin(time * 3.0) + 1.0) / 2.0;
-        vec4 col_kitten = texture(tex_kitten, Texcoord);
-        vec4 col_puppy = texture(tex_puppy, Texcoord);
-        vec4 mixed_texture = mix(col_kitten, col_puppy, mix_factor);
-        out_color = mix(vec4(Color, 1.0), mixed_texture, 0.25);
-    }
-";
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[repr(C, packed)]
-struct Vertex {
-    // Position.
-    x: f32, y: f32,
-
-    // Color.
-    r: f32, g: f32, b: f32,
-
-    // Texture.
-    s: f32, t: f32,
+mod camera;
+mod gl_objects;
+mod math;
+mod obj;
+mod renderer;
+
+use camera::Camera;
+use glfw::{Action, Context, CursorMode, Key, OpenGlProfileHint, WindowHint, WindowMode};
+use renderer::{Renderer, Settings};
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+/// The factor by which each press of `+` or `-` scales the exposure.
+const EXPOSURE_STEP: f32 = 1.1;
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1);
 }
 
-static VERTICES: [Vertex; 4] = [
-    Vertex { x: -0.5, y:  0.5, r: 1.0, g: 0.0, b: 0.0, s: 0.0, t: 0.0 }, // Top-left
-    Vertex { x:  0.5, y:  0.5, r: 0.0, g: 1.0, b: 0.0, s: 1.0, t: 0.0 }, // Top-right
-    Vertex { x:  0.5, y: -0.5, r: 0.0, g: 0.0, b: 1.0, s: 1.0, t: 1.0 }, // Bottom-right
-    Vertex { x: -0.5, y: -0.5, r: 1.0, g: 1.0, b: 1.0, s: 0.0, t: 1.0 }, // Bottom-left
-];
-
-static ELEMENTS: [u32; 6] = [
-    0, 1, 2, // Top-right triangle
-    2, 3, 0, // Bottom-left triangle
-];
-
-unsafe fn compile_shader(shader_type: GLenum, source: &str) -> Result<u32, String> {
-    let shader = gl::CreateShader(shader_type);
-    let source_ptr = source.as_bytes().as_ptr() as *const GLchar;
-    let source_len = source.len() as i32;
-    gl::ShaderSource(shader, 1, &source_ptr, &source_len);
-    gl::CompileShader(shader);
-
-    let mut status = gl::FALSE as i32;
-    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
-
-    if status == gl::TRUE as i32 {
-        Ok(shader)
-    } else {
-        let mut log_len = 0;
-        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut log_len);
-        if log_len == 0 { return Err(String::new()) }
-
-        let mut buf = Vec::with_capacity(log_len as usize);
-        buf.set_len(log_len as usize - 1); // Subtract 1 to ignore the trailing null.
-        gl::GetShaderInfoLog(shader, log_len, ptr::null_mut(), buf.as_mut_ptr() as *mut GLchar);
-
-        Err(String::from_utf8_lossy(&buf).into_owned())
+fn main() {
+    if let Err(err) = run() {
+        exit_with_error(&err);
     }
 }
 
-fn main() {
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+fn run() -> Result<(), String> {
+    // Draw the model in the OBJ file given as the first argument, or a quad if there isn't one.
+    let (vertices, elements) = match env::args().nth(1) {
+        Some(path) => try!(obj::load_obj(&path)),
+        None => (renderer::VERTICES.to_vec(), renderer::ELEMENTS.to_vec()),
+    };
+
+    let mut glfw = try!(glfw::init(glfw::FAIL_ON_ERRORS)
+        .map_err(|err| format!("Failed to initialize GLFW: {:?}", err)));
 
     glfw.window_hint(WindowHint::ContextVersion(3, 2));
     glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(WindowHint::Resizable(false));
+    glfw.window_hint(WindowHint::Resizable(true));
 
-    let (mut window, events) = glfw.create_window(800, 600, "OpenGL", WindowMode::Windowed)
-        .expect("Failed to create GLFW window.");
+    let (mut window, events) = try!(glfw
+        .create_window(800, 600, "OpenGL", WindowMode::Windowed)
+        .ok_or_else(|| "Failed to create GLFW window.".to_string()));
 
-    // Listen for keyboard events on this window.
+    // Listen for keyboard and resize events on this window.
     window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
 
-    // Make this window's OpenGL context the current context. This must be done before calling
-    // `gl::load_with`.
-    window.make_current();
+    // Hide the cursor and keep it in the window so mouse movement can turn the camera freely.
+    window.set_cursor_mode(CursorMode::Disabled);
 
-    // Load OpenGL function pointers.
-    gl::load_with(|symbol| window.get_proc_address(symbol));
-
-    let vertex_shader;
-    let fragment_shader;
-    let shader_program;
-    let mut vao = 0;
-    let mut vbo = 0;
-    let mut ebo = 0;
-    let mut textures = [0; 2];
-
-    unsafe {
-        // Create a vertex array object.
-        gl::GenVertexArrays(1, &mut vao);
-        gl::BindVertexArray(vao);
-
-        // Create a vertex buffer object and copy the vertex data to it.
-        gl::GenBuffers(1, &mut vbo);
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(gl::ARRAY_BUFFER,
-                       mem::size_of_val(&VERTICES) as usize,
-                       VERTICES.as_ptr() as *const (),
-                       gl::STATIC_DRAW);
-
-        // Create an element buffer object and copy the element data to it.
-        gl::GenBuffers(1, &mut ebo);
-        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-        gl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
-                       mem::size_of_val(&ELEMENTS) as usize,
-                       ELEMENTS.as_ptr() as *const (),
-                       gl::STATIC_DRAW);
-
-        // Compile the vertex and fragment shaders.
-        vertex_shader = compile_shader(gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE).unwrap();
-        fragment_shader = compile_shader(gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE).unwrap();
-
-        // Link the vertex and fragment shaders into a shader program.
-        shader_program = gl::CreateProgram();
-        gl::AttachShader(shader_program, vertex_shader);
-        gl::AttachShader(shader_program, fragment_shader);
-        gl::BindFragDataLocation(shader_program, 0, gl_str!("out_color"));
-        gl::LinkProgram(shader_program);
-        gl::UseProgram(shader_program);
-
-        // Specify the layout of the vertex data.
-        let position_attrib = gl::GetAttribLocation(shader_program, gl_str!("position"));
-        gl::EnableVertexAttribArray(position_attrib as u32);
-        gl::VertexAttribPointer(position_attrib as u32, 2, gl::FLOAT, gl::FALSE,
-                                mem::size_of::<Vertex>() as i32, ptr::null());
-
-        let position_attrib = gl::GetAttribLocation(shader_program, gl_str!("color"));
-        gl::EnableVertexAttribArray(position_attrib as u32);
-        gl::VertexAttribPointer(position_attrib as u32, 3, gl::FLOAT, gl::FALSE,
-                                mem::size_of::<Vertex>() as i32,
-                                (2 * mem::size_of::<f32>()) as *const ());
-
-        let position_attrib = gl::GetAttribLocation(shader_program, gl_str!("texcoord"));
-        gl::EnableVertexAttribArray(position_attrib as u32);
-        gl::VertexAttribPointer(position_attrib as u32, 2, gl::FLOAT, gl::FALSE,
-                                mem::size_of::<Vertex>() as i32,
-                                (5 * mem::size_of::<f32>()) as *const ());
-
-        // Create and load textures.
-        gl::GenTextures(2, textures.as_mut_ptr());
-
-        gl::ActiveTexture(gl::TEXTURE0);
-        gl::BindTexture(gl::TEXTURE_2D, textures[0]);
-        let image = imagefmt::read("sample.png", imagefmt::ColFmt::RGB).unwrap();
-        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, image.w as i32, image.h as i32,
-                       0, gl::RGB, gl::UNSIGNED_BYTE, image.buf.as_ptr() as *const ());
-        gl::Uniform1i(gl::GetUniformLocation(shader_program, gl_str!("tex_kitten")), 0);
-
-        gl::GenerateMipmap(gl::TEXTURE_2D);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+    // Make this window's OpenGL context the current context. This must be done before creating
+    // the renderer, which loads the OpenGL function pointers from it.
+    window.make_current();
 
-        gl::ActiveTexture(gl::TEXTURE1);
-        gl::BindTexture(gl::TEXTURE_2D, textures[1]);
-        let image = imagefmt::read("sample2.png", imagefmt::ColFmt::RGB).unwrap();
-        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, image.w as i32, image.h as i32,
-                       0, gl::RGB, gl::UNSIGNED_BYTE, image.buf.as_ptr() as *const ());
-        gl::Uniform1i(gl::GetUniformLocation(shader_program, gl_str!("tex_puppy")), 1);
+    // The renderer's GL objects are deleted when it's dropped at the end of `run`, which happens
+    // before the window (and its context) is dropped since it's declared after it.
+    let mut renderer = try!(Renderer::new(&mut window, &vertices, &elements));
 
-        gl::GenerateMipmap(gl::TEXTURE_2D);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
-    }
+    let mut camera = Camera::looking_at(math::Vec3([1.2, 1.2, 1.2]), math::Vec3::zero());
+    let mut last_cursor_pos = window.get_cursor_pos();
 
-    let view = math::Mat4::look_at(
-        math::Vec3([1.2, 1.2, 1.2]),
-        math::Vec3([0.0, 0.0, 0.0]),
-        math::Vec3([0.0, 0.0, 1.0]));
-    let proj = math::Mat4::perspective(math::TAU / 8.0, 800.0 / 600.0, 1.0, 10.0);
-
-    let trans_uniform = unsafe { gl::GetUniformLocation(shader_program, gl_str!("trans")) };
-    let time_uniform = unsafe { gl::GetUniformLocation(shader_program, gl_str!("time")) };
     let time_start = time::precise_time_ns();
+    let mut time_last_frame = time_start;
 
     while !window.should_close() {
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut window, event);
+            handle_window_event(&mut window, &mut renderer, event);
         }
 
-        unsafe {
-            // Update the `time` uniform.
-            let time_now = time::precise_time_ns();
-            let elapsed_seconds = (time_now - time_start) as f32 / 1e9;
-            gl::Uniform1f(time_uniform, elapsed_seconds);
-
-            // Vary the model matrix over time.
-            let scale = (elapsed_seconds * 5.0).sin() * 0.25 + 0.75;
-            let model =
-                math::Mat4::rotate_z(math::TAU / 2.0 * elapsed_seconds) *
-                math::Mat4::scale(scale, scale, scale);
-            let trans = proj * view * model;
-            gl::UniformMatrix4fv(trans_uniform, 1, gl::FALSE, &trans[0][0]);
-
-            // Clear the screen to black.
-            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-
-            // Draw the triangles described by the elements array.
-            gl::DrawElements(gl::TRIANGLES, ELEMENTS.len() as i32, gl::UNSIGNED_INT,
-                             ptr::null());
+        let time_now = time::precise_time_ns();
+        let elapsed_seconds = (time_now - time_start) as f32 / 1e9;
+        let frame_seconds = (time_now - time_last_frame) as f32 / 1e9;
+        time_last_frame = time_now;
+
+        // Move the camera while movement keys are held, and turn it by how far the mouse moved
+        // since the last frame.
+        for &key in &[Key::W, Key::A, Key::S, Key::D] {
+            if window.get_key(key) == Action::Press {
+                camera.process_keyboard(key, frame_seconds);
+            }
         }
 
+        let cursor_pos = window.get_cursor_pos();
+        camera.process_mouse((cursor_pos.0 - last_cursor_pos.0) as f32,
+                             (cursor_pos.1 - last_cursor_pos.1) as f32);
+        last_cursor_pos = cursor_pos;
+
+        renderer.draw(camera.view_matrix(), elapsed_seconds);
         window.swap_buffers();
     }
 
-    unsafe {
-        gl::DeleteTextures(2, textures.as_ptr());
-        gl::DeleteProgram(shader_program);
-        gl::DeleteShader(fragment_shader);
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteBuffers(1, &ebo);
-        gl::DeleteBuffers(1, &vbo);
-        gl::DeleteVertexArrays(1, &vao);
-    }
+    Ok(())
 }
 
-fn handle_window_event(window: &mut glfw::Window, event: glfw::WindowEvent) {
-    use glfw::{Action, Key, WindowEvent};
+fn handle_window_event(window: &mut glfw::Window, renderer: &mut Renderer,
+                       event: glfw::WindowEvent) {
+    use glfw::WindowEvent;
 
     match event {
         WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
             window.set_should_close(true);
         },
+        WindowEvent::FramebufferSize(width, height) => {
+            renderer.resize(width, height);
+        },
+        WindowEvent::Key(Key::Equal, _, Action::Press, _) |
+        WindowEvent::Key(Key::Equal, _, Action::Repeat, _) |
+        WindowEvent::Key(Key::KpAdd, _, Action::Press, _) |
+        WindowEvent::Key(Key::KpAdd, _, Action::Repeat, _) => {
+            renderer.settings.exposure *= EXPOSURE_STEP;
+        },
+        WindowEvent::Key(Key::Minus, _, Action::Press, _) |
+        WindowEvent::Key(Key::Minus, _, Action::Repeat, _) |
+        WindowEvent::Key(Key::KpSubtract, _, Action::Press, _) |
+        WindowEvent::Key(Key::KpSubtract, _, Action::Repeat, _) => {
+            renderer.settings.exposure /= EXPOSURE_STEP;
+        },
+        WindowEvent::Key(Key::F, _, Action::Press, _) => {
+            renderer.settings.wireframe = !renderer.settings.wireframe;
+        },
+        WindowEvent::Key(Key::C, _, Action::Press, _) => {
+            renderer.settings.cull_back_faces = !renderer.settings.cull_back_faces;
+        },
+        WindowEvent::Key(Key::Backspace, _, Action::Press, _) => {
+            renderer.settings = Settings::default();
+        },
+        WindowEvent::Key(Key::R, _, Action::Press, _) => {
+            match renderer.reload_shaders() {
+                Ok(()) => println!("Reloaded shaders."),
+                Err(err) => {
+                    let _ = writeln!(io::stderr(), "Failed to reload shaders:\n{}", err);
+                },
+            }
+        },
         _ => {},
     }
 }
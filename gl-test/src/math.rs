@@ -4,10 +4,10 @@ use std::ops::{Add, Index, IndexMut, Mul, Sub};
 pub const TAU: f32 = 2.0 * PI;
 
 macro_rules! define_vec {
-    ($name:ident, $size:expr) => (
+    ($name:ident, $scalar:ident, $size:expr) => (
         /// A column vector.
         #[derive(Copy, Clone, Debug, PartialEq)]
-        pub struct $name(pub [f32; $size]);
+        pub struct $name(pub [$scalar; $size]);
 
         impl $name {
             /// Create a vector with all fields set to zero.
@@ -17,26 +17,46 @@ macro_rules! define_vec {
 
             /// Calculate the square of the length (or norm) of the vector. Slightly faster than
             /// `length`.
-            pub fn length_squared(self) -> f32 {
+            pub fn length_squared(self) -> $scalar {
                 self.dot(self)
             }
 
             /// Calculate the the length (or norm) of the vector.
-            pub fn length(self) -> f32 {
+            pub fn length(self) -> $scalar {
                 self.length_squared().sqrt()
             }
 
-            /// Normalize the vector so that it has the same orientation but a length of 1.
+            /// Calculate the distance between two points.
+            pub fn distance(self, other: Self) -> $scalar {
+                (other - self).length()
+            }
+
+            /// Normalize the vector so that it has the same orientation but a length of 1. A
+            /// zero-length vector has no orientation, so it is left unchanged.
             pub fn normalize(&mut self) {
+                *self = self.normalized();
+            }
+
+            /// Return a vector with the same orientation as this one but a length of 1. A
+            /// zero-length vector has no orientation, so it is returned unchanged.
+            pub fn normalized(self) -> Self {
                 let length = self.length();
 
+                if length == 0.0 {
+                    return self;
+                }
+
+                let mut result = self;
+
                 for i in 0..$size {
-                    self[i] /= length;
+                    result[i] /= length;
                 }
+
+                result
             }
 
             /// Calculate the vector dot product.
-            pub fn dot(self, other: Self) -> f32 {
+            pub fn dot(self, other: Self) -> $scalar {
                 let mut result = 0.0;
 
                 for i in 0..$size {
@@ -45,18 +65,42 @@ macro_rules! define_vec {
 
                 result
             }
+
+            /// Check whether each component of `self` is within `epsilon` of the matching
+            /// component of `other`. Useful where rounding error makes `==` too strict.
+            pub fn approx_eq(self, other: Self, epsilon: $scalar) -> bool {
+                (0..$size).all(|i| (self[i] - other[i]).abs() <= epsilon)
+            }
+
+            /// Linearly interpolate between `self` and `other` by `t`, without clamping `t`.
+            /// Matches GLSL's `mix`.
+            pub fn mix(self, other: Self, t: $scalar) -> Self {
+                let mut result = $name::zero();
+
+                for i in 0..$size {
+                    result[i] = self[i] * (1.0 - t) + other[i] * t;
+                }
+
+                result
+            }
+
+            /// Linearly interpolate from `self` (at `t = 0`) to `other` (at `t = 1`). Values of `t`
+            /// outside that range extrapolate, so clamp first if that isn't wanted.
+            pub fn lerp(self, other: Self, t: $scalar) -> Self {
+                self + (other - self) * t
+            }
         }
 
         impl Index<usize> for $name {
-            type Output = f32;
+            type Output = $scalar;
 
-            fn index(&self, i: usize) -> &f32 {
+            fn index(&self, i: usize) -> &$scalar {
                 &self.0[i]
             }
         }
 
         impl IndexMut<usize> for $name {
-            fn index_mut(&mut self, i: usize) -> &mut f32 {
+            fn index_mut(&mut self, i: usize) -> &mut $scalar {
                 &mut self.0[i]
             }
         }
@@ -88,203 +132,704 @@ macro_rules! define_vec {
                 result
             }
         }
+
+        impl Mul<$scalar> for $name {
+            type Output = Self;
+
+            fn mul(self, scalar: $scalar) -> Self {
+                let mut result = $name::zero();
+
+                for i in 0..$size {
+                    result[i] = self[i] * scalar;
+                }
+
+                result
+            }
+        }
+
+        impl Mul<$name> for $scalar {
+            type Output = $name;
+
+            fn mul(self, vec: $name) -> $name {
+                vec * self
+            }
+        }
     );
 }
 
-define_vec!(Vec3, 3);
-define_vec!(Vec4, 4);
-
-impl Vec3 {
-    /// Calculate the vector cross product.
-    pub fn cross(self, other: Self) -> Self {
-        Vec3([
-            self[1] * other[2] - self[2] * other[1],
-            self[2] * other[0] - self[0] * other[2],
-            self[0] * other[1] - self[1] * other[0],
-        ])
-    }
-}
+macro_rules! define_math {
+    ($vec3:ident, $vec4:ident, $mat4:ident, $quat:ident, $scalar:ident) => (
+        define_vec!($vec3, $scalar, 3);
+        define_vec!($vec4, $scalar, 4);
 
-/// A matrix stored in column-major order.
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Mat4(pub [[f32; 4]; 4]);
-
-impl Mat4 {
-    /// The zero matrix.
-    pub fn zero() -> Self {
-        Mat4([
-            [0.0, 0.0, 0.0, 0.0],
-            [0.0, 0.0, 0.0, 0.0],
-            [0.0, 0.0, 0.0, 0.0],
-            [0.0, 0.0, 0.0, 0.0],
-        ])
-    }
+        impl $vec3 {
+            /// Calculate the vector cross product.
+            pub fn cross(self, other: Self) -> Self {
+                $vec3([
+                    self[1] * other[2] - self[2] * other[1],
+                    self[2] * other[0] - self[0] * other[2],
+                    self[0] * other[1] - self[1] * other[0],
+                ])
+            }
 
-    /// The identity matrix.
-    pub fn identity() -> Self {
-        Mat4([
-            [1.0, 0.0, 0.0, 0.0],
-            [0.0, 1.0, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ])
-    }
+            /// Extend to homogeneous coordinates as a point (`w = 1`), which is affected by
+            /// translation.
+            pub fn to_point(self) -> $vec4 {
+                $vec4([self[0], self[1], self[2], 1.0])
+            }
 
-    /// Build a matrix representing a scaling by the given factors.
-    pub fn scale(x: f32, y: f32, z: f32) -> Self {
-        Mat4([
-            [x,   0.0, 0.0, 0.0],
-            [0.0, y,   0.0, 0.0],
-            [0.0, 0.0, z,   0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ])
-    }
+            /// Extend to homogeneous coordinates as a direction (`w = 0`), which is unaffected by
+            /// translation.
+            pub fn to_direction(self) -> $vec4 {
+                $vec4([self[0], self[1], self[2], 0.0])
+            }
 
-    /// Build a matrix representing a translation.
-    pub fn translate(x: f32, y: f32, z: f32) -> Self {
-        Mat4([
-            [1.0, 0.0, 0.0, 0.0],
-            [0.0, 1.0, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [x,   y,   z,   1.0],
-        ])
-    }
+            /// Find two unit vectors perpendicular to this one and to each other. Together with
+            /// `self` (normalized), `(self, a, b)` forms a right-handed orthonormal basis. `self`
+            /// must not be zero.
+            pub fn orthonormal_basis(self) -> ($vec3, $vec3) {
+                // Cross with whichever axis is least aligned with `self`, so the result is never
+                // close to zero length.
+                let abs = [self[0].abs(), self[1].abs(), self[2].abs()];
+                let helper = if abs[0] <= abs[1] && abs[0] <= abs[2] {
+                    $vec3([1.0, 0.0, 0.0])
+                } else if abs[1] <= abs[2] {
+                    $vec3([0.0, 1.0, 0.0])
+                } else {
+                    $vec3([0.0, 0.0, 1.0])
+                };
 
-    /// Build a matrix representing a rotation around the X-axis by the given angle (in radians).
-    pub fn rotate_x(angle: f32) -> Self {
-        let cos = angle.cos();
-        let sin = angle.sin();
-
-        Mat4([
-            [1.0, 0.0,  0.0, 0.0],
-            [0.0, cos, -sin, 0.0],
-            [0.0, sin,  cos, 0.0],
-            [0.0, 0.0,  0.0, 1.0],
-        ])
-    }
+                let a = self.cross(helper).normalized();
+                let b = self.normalized().cross(a);
+                (a, b)
+            }
 
-    /// Build a matrix representing a rotation around the Y-axis by the given angle (in radians).
-    pub fn rotate_y(angle: f32) -> Self {
-        let cos = angle.cos();
-        let sin = angle.sin();
-
-        Mat4([
-            [ cos, 0.0, sin, 0.0],
-            [ 0.0, 1.0, 0.0, 0.0],
-            [-sin, 0.0, cos, 0.0],
-            [ 0.0, 0.0, 0.0, 1.0],
-        ])
-    }
+            /// Reflect this incident vector off a surface with the given unit normal. Matches
+            /// GLSL's `reflect`.
+            pub fn reflect(self, normal: $vec3) -> $vec3 {
+                self - 2.0 * self.dot(normal) * normal
+            }
 
-    /// Build a matrix representing a rotation around the Z-axis by the given angle (in radians).
-    pub fn rotate_z(angle: f32) -> Self {
-        let cos = angle.cos();
-        let sin = angle.sin();
-
-        Mat4([
-            [cos, -sin, 0.0, 0.0],
-            [sin,  cos, 0.0, 0.0],
-            [0.0,  0.0, 1.0, 0.0],
-            [0.0,  0.0, 0.0, 1.0],
-        ])
-    }
+            /// Refract this unit incident vector through a surface with the given unit normal,
+            /// where `eta` is the ratio of the indices of refraction (incident over transmitted).
+            /// Returns `None` on total internal reflection. Otherwise matches GLSL's `refract`.
+            pub fn refract(self, normal: $vec3, eta: $scalar) -> Option<$vec3> {
+                let cos_incident = self.dot(normal);
+                let k = 1.0 - eta * eta * (1.0 - cos_incident * cos_incident);
 
-    /// Build a camera view matrix with the camera at `eye` looking toward `center` with `up` as
-    /// the vertical direction.
-    pub fn look_at(eye: Vec3, center: Vec3, up: Vec3) -> Self {
-        // Set the Z-axis to the unit vector pointing from the center toward the eye (the depth
-        // axis).
-        let mut z = eye - center;
-        z.normalize();
-
-        // Make the Y-axis the vertical direction.
-        let mut y = up;
-
-        // Make the X-axis perpendicular to Y and Z, pointing to the right.
-        let mut x = y.cross(z);
-
-        // Make the Y-axis perpendicular to Z and X.
-        y = z.cross(x);
-
-        // Normalize the axes to unit vectors.
-        x.normalize();
-        y.normalize();
-
-        // Build the rotation/translation matrix that transforms coordinates to the new coordinate
-        // system.
-        Mat4([
-            [ x[0],        y[0],        z[0],       0.0],
-            [ x[1],        y[1],        z[1],       0.0],
-            [ x[2],        y[2],        z[2],       0.0],
-            [-x.dot(eye), -y.dot(eye), -z.dot(eye), 1.0],
-        ])
-    }
+                if k < 0.0 {
+                    None
+                } else {
+                    Some(eta * self - (eta * cos_incident + k.sqrt()) * normal)
+                }
+            }
 
-    /// Build a perspective projection matrix with the given vertical field of view (in radians),
-    /// aspect ratio, and Z-axis clipping distances.
-    pub fn perspective(fov_y: f32, aspect: f32, z_near: f32, z_far: f32) -> Self {
-        assert!(aspect != 0.0);
-        assert!(z_near != z_far);
-
-        let f = 1.0 / (fov_y / 2.0).tan();
-        let z_diff = z_near - z_far;
-
-        let mut result = Mat4::zero();
-        result[0][0] = f / aspect;
-        result[1][1] = f;
-        result[2][2] = (z_near + z_far) / z_diff;
-        result[2][3] = -1.0;
-        result[3][2] = (2.0 * z_near * z_far) / z_diff;
-        result
-    }
-}
+            /// Calculate the angle (in radians, from 0 to pi) between this vector and `other`.
+            /// Returns 0 if either is zero length.
+            pub fn angle_between(self, other: $vec3) -> $scalar {
+                if self.length_squared() == 0.0 || other.length_squared() == 0.0 {
+                    return 0.0;
+                }
 
-impl Index<usize> for Mat4 {
-    type Output = [f32; 4];
+                // More accurate than the `acos` of the normalized dot product near 0 and pi,
+                // and never outside its domain.
+                self.cross(other).length().atan2(self.dot(other))
+            }
 
-    fn index(&self, col: usize) -> &[f32; 4] {
-        &self.0[col]
-    }
-}
+            /// Calculate the angle (in radians, from -pi to pi) to turn this vector toward `other`
+            /// around `axis`. The angle is positive for a counterclockwise turn when looking down
+            /// `axis` toward the origin. Returns 0 if either vector is zero length.
+            pub fn signed_angle_around(self, other: $vec3, axis: $vec3) -> $scalar {
+                let angle = self.angle_between(other);
 
-impl IndexMut<usize> for Mat4 {
-    fn index_mut(&mut self, col: usize) -> &mut [f32; 4] {
-        &mut self.0[col]
-    }
-}
+                if self.cross(other).dot(axis) < 0.0 { -angle } else { angle }
+            }
+        }
+
+        /// Treat the vector as a point (`w = 1`). Use `to_direction` for directions.
+        impl From<$vec3> for $vec4 {
+            fn from(vec: $vec3) -> $vec4 {
+                vec.to_point()
+            }
+        }
+
+        /// Drop the `w` component, first dividing through by it unless it's zero (as it is for
+        /// directions).
+        impl From<$vec4> for $vec3 {
+            fn from(vec: $vec4) -> $vec3 {
+                let w = if vec[3] == 0.0 { 1.0 } else { vec[3] };
+                $vec3([vec[0] / w, vec[1] / w, vec[2] / w])
+            }
+        }
+
+        /// A matrix stored in column-major order.
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        pub struct $mat4(pub [[$scalar; 4]; 4]);
+
+        impl $mat4 {
+            /// The zero matrix.
+            pub fn zero() -> Self {
+                $mat4([
+                    [0.0, 0.0, 0.0, 0.0],
+                    [0.0, 0.0, 0.0, 0.0],
+                    [0.0, 0.0, 0.0, 0.0],
+                    [0.0, 0.0, 0.0, 0.0],
+                ])
+            }
+
+            /// The identity matrix.
+            pub fn identity() -> Self {
+                $mat4([
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+
+            /// Build a matrix representing a scaling by the given factors.
+            pub fn scale(x: $scalar, y: $scalar, z: $scalar) -> Self {
+                $mat4([
+                    [x,   0.0, 0.0, 0.0],
+                    [0.0, y,   0.0, 0.0],
+                    [0.0, 0.0, z,   0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+
+            /// Build a matrix representing a translation.
+            pub fn translate(x: $scalar, y: $scalar, z: $scalar) -> Self {
+                $mat4([
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [x,   y,   z,   1.0],
+                ])
+            }
+
+            /// Build a matrix representing a rotation around the X-axis by the given angle (in
+            /// radians).
+            pub fn rotate_x(angle: $scalar) -> Self {
+                let cos = angle.cos();
+                let sin = angle.sin();
+
+                $mat4([
+                    [1.0, 0.0,  0.0, 0.0],
+                    [0.0, cos, -sin, 0.0],
+                    [0.0, sin,  cos, 0.0],
+                    [0.0, 0.0,  0.0, 1.0],
+                ])
+            }
+
+            /// Build a matrix representing a rotation around the Y-axis by the given angle (in
+            /// radians).
+            pub fn rotate_y(angle: $scalar) -> Self {
+                let cos = angle.cos();
+                let sin = angle.sin();
+
+                $mat4([
+                    [ cos, 0.0, sin, 0.0],
+                    [ 0.0, 1.0, 0.0, 0.0],
+                    [-sin, 0.0, cos, 0.0],
+                    [ 0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+
+            /// Build a matrix representing a rotation around the Z-axis by the given angle (in
+            /// radians).
+            pub fn rotate_z(angle: $scalar) -> Self {
+                let cos = angle.cos();
+                let sin = angle.sin();
+
+                $mat4([
+                    [cos, -sin, 0.0, 0.0],
+                    [sin,  cos, 0.0, 0.0],
+                    [0.0,  0.0, 1.0, 0.0],
+                    [0.0,  0.0, 0.0, 1.0],
+                ])
+            }
+
+            /// Build a camera view matrix with the camera at `eye` looking toward `center` with
+            /// `up` as the vertical direction.
+            pub fn look_at(eye: $vec3, center: $vec3, up: $vec3) -> Self {
+                // Set the Z-axis to the unit vector pointing from the center toward the eye (the
+                // depth axis).
+                let z = (eye - center).normalized();
+
+                // Make the X-axis perpendicular to the vertical direction and Z, pointing to the
+                // right.
+                let x = up.cross(z).normalized();
 
-impl Mul<Mat4> for Mat4 {
-    type Output = Mat4;
+                // Make the Y-axis perpendicular to Z and X. They're perpendicular unit vectors, so
+                // their cross product is already unit length.
+                let y = z.cross(x);
 
-    fn mul(self, other: Mat4) -> Mat4 {
-        let mut result = Mat4::zero();
+                // Build the rotation/translation matrix that transforms coordinates to the new
+                // coordinate system.
+                $mat4([
+                    [ x[0],        y[0],        z[0],       0.0],
+                    [ x[1],        y[1],        z[1],       0.0],
+                    [ x[2],        y[2],        z[2],       0.0],
+                    [-x.dot(eye), -y.dot(eye), -z.dot(eye), 1.0],
+                ])
+            }
+
+            /// Build a perspective projection matrix with the given vertical field of view (in
+            /// radians), aspect ratio, and Z-axis clipping distances.
+            pub fn perspective(fov_y: $scalar, aspect: $scalar, z_near: $scalar, z_far: $scalar)
+                               -> Self {
+                assert!(aspect != 0.0);
+                assert!(z_near != z_far);
+
+                let f = 1.0 / (fov_y / 2.0).tan();
+                let z_diff = z_near - z_far;
+
+                let mut result = $mat4::zero();
+                result[0][0] = f / aspect;
+                result[1][1] = f;
+                result[2][2] = (z_near + z_far) / z_diff;
+                result[2][3] = -1.0;
+                result[3][2] = (2.0 * z_near * z_far) / z_diff;
+                result
+            }
+
+            /// Build an orthographic projection matrix mapping the given box to the clip-space
+            /// cube.
+            pub fn ortho(left: $scalar, right: $scalar, bottom: $scalar, top: $scalar,
+                         z_near: $scalar, z_far: $scalar) -> Self {
+                assert!(left != right);
+                assert!(bottom != top);
+                assert!(z_near != z_far);
+
+                let width = right - left;
+                let height = top - bottom;
+                let depth = z_far - z_near;
+
+                let mut result = $mat4::identity();
+                result[0][0] = 2.0 / width;
+                result[1][1] = 2.0 / height;
+                result[2][2] = -2.0 / depth;
+                result[3][0] = -(right + left) / width;
+                result[3][1] = -(top + bottom) / height;
+                result[3][2] = -(z_far + z_near) / depth;
+                result
+            }
+
+            /// Compose this transformation with `next`, so that `self` is applied first and `next`
+            /// second. Equivalent to `next * self`, but lets chains be read in the order they
+            /// apply, e.g. `scale.then(rotate).then(translate)`.
+            pub fn then(self, next: $mat4) -> $mat4 {
+                next * self
+            }
+
+            /// Transform a point (treated as `w = 1`), including the perspective divide.
+            pub fn transform_point(self, point: $vec3) -> $vec3 {
+                $vec3::from(self * point.to_point())
+            }
+
+            /// Transform a direction (treated as `w = 0`), so translation has no effect.
+            pub fn transform_direction(self, dir: $vec3) -> $vec3 {
+                let result = self * dir.to_direction();
+                $vec3([result[0], result[1], result[2]])
+            }
+
+            /// Check whether each element of `self` is within `epsilon` of the matching element of
+            /// `other`. Useful where rounding error makes `==` too strict.
+            pub fn approx_eq(self, other: $mat4, epsilon: $scalar) -> bool {
+                (0..4).all(|col| $vec4(self[col]).approx_eq($vec4(other[col]), epsilon))
+            }
+
+            /// Swap the rows and columns of the matrix.
+            pub fn transpose(self) -> $mat4 {
+                let mut result = $mat4::zero();
+
+                for col in 0..4 {
+                    for row in 0..4 {
+                        result[col][row] = self[row][col];
+                    }
+                }
+
+                result
+            }
+
+            /// Calculate the determinant of the matrix.
+            pub fn determinant(self) -> $scalar {
+                let mut result = 0.0;
+
+                for col in 0..4 {
+                    result += self[col][0] * self.cofactor(col, 0);
+                }
 
-        for col in 0..4 {
-            for row in 0..4 {
-                for i in 0..4 {
-                    result[col][row] += self[i][row] * other[col][i];
+                result
+            }
+
+            /// Calculate the inverse of the matrix, or `None` if the matrix is singular (its
+            /// determinant is zero) or has non-finite elements. The determinant scales with the
+            /// cube of the matrix's scale, so it isn't compared against a fixed tolerance, which
+            /// would reject small but perfectly invertible scales.
+            pub fn inverse(self) -> Option<$mat4> {
+                let det = self.determinant();
+
+                if det == 0.0 || !det.is_finite() {
+                    return None;
                 }
+
+                // The inverse is the transpose of the cofactor matrix (the adjugate) divided by the
+                // determinant.
+                let mut result = $mat4::zero();
+
+                for col in 0..4 {
+                    for row in 0..4 {
+                        result[col][row] = self.cofactor(row, col) / det;
+                    }
+                }
+
+                Some(result)
+            }
+
+            /// Split an affine transform into its translation, rotation, and scale, such that
+            /// `translate(t) * rotation.to_mat4() * scale(s)` rebuilds it. The matrix must not
+            /// contain shear or projection, and each axis must have nonzero scale.
+            ///
+            /// A reflection can't be represented by a rotation, so if the matrix has one (a
+            /// negative determinant) it's returned as a negative X scale instead.
+            pub fn decompose(self) -> ($vec3, $quat, $vec3) {
+                let translation = $vec3([self[3][0], self[3][1], self[3][2]]);
+
+                // The first three columns are the transformed basis vectors, whose lengths are
+                // the scale along each axis.
+                let mut axes = [$vec3::zero(); 3];
+                let mut scale = $vec3::zero();
+                for i in 0..3 {
+                    axes[i] = $vec3([self[i][0], self[i][1], self[i][2]]);
+                    scale[i] = axes[i].length();
+                }
+
+                if self.determinant() < 0.0 {
+                    scale[0] = -scale[0];
+                }
+
+                // Dividing out the scale leaves a pure rotation matrix.
+                let mut rotation = $mat4::identity();
+                for i in 0..3 {
+                    let axis = axes[i] * (1.0 / scale[i]);
+                    rotation[i] = [axis[0], axis[1], axis[2], 0.0];
+                }
+
+                (translation, $quat::from_mat4(rotation), scale)
+            }
+
+            /// Calculate the signed determinant of the 3x3 matrix left after removing the given
+            /// column and row.
+            fn cofactor(self, col: usize, row: usize) -> $scalar {
+                let mut minor = [[0.0; 3]; 3];
+
+                for (minor_col, c) in (0..4).filter(|&c| c != col).enumerate() {
+                    for (minor_row, r) in (0..4).filter(|&r| r != row).enumerate() {
+                        minor[minor_col][minor_row] = self[c][r];
+                    }
+                }
+
+                let det =
+                    minor[0][0] * (minor[1][1] * minor[2][2] - minor[2][1] * minor[1][2]) -
+                    minor[1][0] * (minor[0][1] * minor[2][2] - minor[2][1] * minor[0][2]) +
+                    minor[2][0] * (minor[0][1] * minor[1][2] - minor[1][1] * minor[0][2]);
+
+                if (col + row) % 2 == 0 { det } else { -det }
             }
         }
 
-        result
-    }
+        impl Index<usize> for $mat4 {
+            type Output = [$scalar; 4];
+
+            fn index(&self, col: usize) -> &[$scalar; 4] {
+                &self.0[col]
+            }
+        }
+
+        impl IndexMut<usize> for $mat4 {
+            fn index_mut(&mut self, col: usize) -> &mut [$scalar; 4] {
+                &mut self.0[col]
+            }
+        }
+
+        impl Mul<$mat4> for $mat4 {
+            type Output = $mat4;
+
+            fn mul(self, other: $mat4) -> $mat4 {
+                let mut result = $mat4::zero();
+
+                for col in 0..4 {
+                    for row in 0..4 {
+                        for i in 0..4 {
+                            result[col][row] += self[i][row] * other[col][i];
+                        }
+                    }
+                }
+
+                result
+            }
+        }
+
+        impl Mul<$scalar> for $mat4 {
+            type Output = $mat4;
+
+            fn mul(self, scalar: $scalar) -> $mat4 {
+                let mut result = $mat4::zero();
+
+                for col in 0..4 {
+                    for row in 0..4 {
+                        result[col][row] = self[col][row] * scalar;
+                    }
+                }
+
+                result
+            }
+        }
+
+        impl Mul<$mat4> for $scalar {
+            type Output = $mat4;
+
+            fn mul(self, mat: $mat4) -> $mat4 {
+                mat * self
+            }
+        }
+
+        impl Mul<$vec4> for $mat4 {
+            type Output = $vec4;
+
+            fn mul(self, vec: $vec4) -> $vec4 {
+                let mut result = $vec4::zero();
+
+                for col in 0..4 {
+                    for row in 0..4 {
+                        result[row] += self[col][row] * vec[col];
+                    }
+                }
+
+                result
+            }
+        }
+
+        /// A quaternion, used to represent rotations without the gimbal lock of composed axis
+        /// rotations.
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        pub struct $quat {
+            pub w: $scalar,
+            pub x: $scalar,
+            pub y: $scalar,
+            pub z: $scalar,
+        }
+
+        impl $quat {
+            /// The quaternion representing no rotation.
+            pub fn identity() -> Self {
+                $quat { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+            }
+
+            /// Build a quaternion representing a rotation around `axis` by the given angle (in
+            /// radians). The direction of rotation matches `Mat4::rotate_x`, `rotate_y`, and
+            /// `rotate_z`, so `Quaternion::from_axis_angle(Vec3([0.0, 0.0, 1.0]), angle).to_mat4()`
+            /// is `Mat4::rotate_z(angle)`.
+            pub fn from_axis_angle(axis: $vec3, angle: $scalar) -> Self {
+                // The `rotate_*` helpers turn clockwise when looking down the axis toward the
+                // origin, which is the opposite of the usual quaternion convention, hence the
+                // negated angle.
+                let axis = axis.normalized();
+                let half_angle = -angle / 2.0;
+                let sin = half_angle.sin();
+
+                $quat {
+                    w: half_angle.cos(),
+                    x: axis[0] * sin,
+                    y: axis[1] * sin,
+                    z: axis[2] * sin,
+                }
+            }
+
+            /// Build the quaternion for the rotation in the upper-left 3x3 of `m`, which must be a
+            /// pure rotation matrix. This is the inverse of `to_mat4`.
+            pub fn from_mat4(m: $mat4) -> Self {
+                // Solve for whichever component has the largest magnitude first, since the others
+                // are found by dividing by it.
+                let trace = m[0][0] + m[1][1] + m[2][2];
+
+                if trace > 0.0 {
+                    let s = (trace + 1.0).sqrt() * 2.0;
+                    $quat {
+                        w: 0.25 * s,
+                        x: (m[1][2] - m[2][1]) / s,
+                        y: (m[2][0] - m[0][2]) / s,
+                        z: (m[0][1] - m[1][0]) / s,
+                    }
+                } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+                    let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+                    $quat {
+                        w: (m[1][2] - m[2][1]) / s,
+                        x: 0.25 * s,
+                        y: (m[1][0] + m[0][1]) / s,
+                        z: (m[2][0] + m[0][2]) / s,
+                    }
+                } else if m[1][1] > m[2][2] {
+                    let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+                    $quat {
+                        w: (m[2][0] - m[0][2]) / s,
+                        x: (m[1][0] + m[0][1]) / s,
+                        y: 0.25 * s,
+                        z: (m[2][1] + m[1][2]) / s,
+                    }
+                } else {
+                    let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+                    $quat {
+                        w: (m[0][1] - m[1][0]) / s,
+                        x: (m[2][0] + m[0][2]) / s,
+                        y: (m[2][1] + m[1][2]) / s,
+                        z: 0.25 * s,
+                    }
+                }
+            }
+
+            /// Calculate the length (or norm) of the quaternion.
+            pub fn length(self) -> $scalar {
+                (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+            }
+
+            /// Normalize the quaternion to unit length, which is required for it to represent a
+            /// pure rotation. Useful for correcting error accumulated by repeated multiplication. A
+            /// zero quaternion is left unchanged.
+            pub fn normalize(&mut self) {
+                *self = self.normalized();
+            }
+
+            /// Return a unit-length copy of this quaternion. A zero quaternion is returned
+            /// unchanged.
+            pub fn normalized(self) -> Self {
+                let length = self.length();
+
+                if length == 0.0 {
+                    return self;
+                }
+
+                $quat {
+                    w: self.w / length,
+                    x: self.x / length,
+                    y: self.y / length,
+                    z: self.z / length,
+                }
+            }
+
+            /// Build the rotation matrix equivalent to this (unit) quaternion.
+            pub fn to_mat4(self) -> $mat4 {
+                let $quat { w, x, y, z } = self;
+                let (xx, yy, zz) = (x * x, y * y, z * z);
+                let (xy, xz, yz) = (x * y, x * z, y * z);
+                let (wx, wy, wz) = (w * x, w * y, w * z);
+
+                $mat4([
+                    [1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz),       2.0 * (xz - wy),       0.0],
+                    [2.0 * (xy - wz),       1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx),       0.0],
+                    [2.0 * (xz + wy),       2.0 * (yz - wx),       1.0 - 2.0 * (xx + yy), 0.0],
+                    [0.0,                   0.0,                   0.0,                   1.0],
+                ])
+            }
+        }
+
+        /// The Hamilton product. As with matrices, `a * b` represents applying `b` first, then `a`.
+        impl Mul<$quat> for $quat {
+            type Output = $quat;
+
+            fn mul(self, other: $quat) -> $quat {
+                let (a, b) = (self, other);
+
+                $quat {
+                    w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+                    x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+                    y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+                    z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+                }
+            }
+        }
+    );
 }
 
-impl Mul<Vec4> for Mat4 {
-    type Output = Vec4;
+/// Define `From` conversions from one precision's types to another's.
+macro_rules! define_conversions {
+    ($from_vec3:ident, $from_vec4:ident, $from_mat4:ident,
+     $vec3:ident, $vec4:ident, $mat4:ident, $scalar:ident) => (
+        impl From<$from_vec3> for $vec3 {
+            fn from(vec: $from_vec3) -> $vec3 {
+                $vec3([vec[0] as $scalar, vec[1] as $scalar, vec[2] as $scalar])
+            }
+        }
 
-    fn mul(self, vec: Vec4) -> Vec4 {
-        let mut result = Vec4::zero();
+        impl From<$from_vec4> for $vec4 {
+            fn from(vec: $from_vec4) -> $vec4 {
+                $vec4([vec[0] as $scalar, vec[1] as $scalar, vec[2] as $scalar, vec[3] as $scalar])
+            }
+        }
+
+        impl From<$from_mat4> for $mat4 {
+            fn from(mat: $from_mat4) -> $mat4 {
+                let mut result = $mat4::zero();
+
+                for col in 0..4 {
+                    result[col] = $vec4::from($from_vec4(mat[col])).0;
+                }
 
-        for col in 0..4 {
-            for row in 0..4 {
-                result[row] += self[col][row] * vec[col];
+                result
             }
         }
+    );
+}
+
+// The single-precision types match `GLfloat` and are what gets uploaded to the GPU. The
+// double-precision types are for CPU-side work that needs the extra accuracy, and can be
+// converted with `From`/`Into` when it's time to upload.
+define_math!(Vec3, Vec4, Mat4, Quaternion, f32);
+define_math!(Vec3d, Vec4d, Mat4d, Quaterniond, f64);
+define_conversions!(Vec3d, Vec4d, Mat4d, Vec3, Vec4, Mat4, f32);
+define_conversions!(Vec3, Vec4, Mat4, Vec3d, Vec4d, Mat4d, f64);
 
-        result
+/// Find a bounding sphere for the given points using Ritter's algorithm, returning its center and
+/// radius. The result is not guaranteed to be minimal, but is typically much tighter than a
+/// sphere around the bounding box.
+pub fn bounding_sphere(points: &[Vec3]) -> (Vec3, f32) {
+    if points.is_empty() {
+        return (Vec3::zero(), 0.0);
     }
+
+    // Find a point far from an arbitrary starting point, then the point farthest from that. The
+    // two are used as the initial diameter.
+    let farthest_from = |origin: Vec3| {
+        let mut farthest = origin;
+
+        for &p in points {
+            if origin.distance(p) > origin.distance(farthest) {
+                farthest = p;
+            }
+        }
+
+        farthest
+    };
+    let a = farthest_from(points[0]);
+    let b = farthest_from(a);
+
+    let mut center = a.mix(b, 0.5);
+    let mut radius = a.distance(b) / 2.0;
+
+    // Grow the sphere just enough to cover any point still outside it, keeping the far side of
+    // the sphere fixed.
+    for &p in points {
+        let dist = center.distance(p);
+
+        if dist > radius {
+            let new_radius = (radius + dist) / 2.0;
+            center = center.mix(p, (new_radius - radius) / dist);
+            radius = new_radius;
+        }
+    }
+
+    (center, radius)
 }
 
 #[test]
@@ -298,3 +843,386 @@ fn test_math() {
 
     assert_eq!(expected, combined * original);
 }
+
+#[test]
+fn test_mix() {
+    // The same blend the fragment shader does with `mix(vec4(Color, 1.0), mixed_texture, 0.25)`.
+    let color = Vec3([1.0, 0.0, 0.5]);
+    let texture = Vec3([0.0, 1.0, 0.5]);
+
+    assert_eq!(Vec3([0.75, 0.25, 0.5]), color.mix(texture, 0.25));
+    assert_eq!(color, color.mix(texture, 0.0));
+    assert_eq!(texture, color.mix(texture, 1.0));
+}
+
+#[test]
+fn test_bounding_sphere() {
+    let points = [
+        Vec3([-1.0, 0.0, 0.0]),
+        Vec3([ 1.0, 0.0, 0.0]),
+        Vec3([ 0.0, 0.9, 0.0]),
+        Vec3([ 0.0, 0.0, 1.1]),
+        Vec3([ 0.5, 0.5, 0.5]),
+        Vec3([-0.7, -0.7, -0.3]),
+    ];
+
+    let (center, radius) = bounding_sphere(&points);
+
+    for &p in &points {
+        assert!(center.distance(p) <= radius + 1e-5);
+    }
+
+    assert_eq!((Vec3::zero(), 0.0), bounding_sphere(&[]));
+}
+
+#[test]
+fn test_then() {
+    let scale = Mat4::scale(2.0, 2.0, 2.0);
+    let trans = Mat4::translate(1.0, 2.0, 3.0);
+
+    let origin = Vec4([0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(trans * scale * origin, scale.then(trans) * origin);
+    assert_eq!(Vec4([1.0, 2.0, 3.0, 1.0]), scale.then(trans) * origin);
+
+    let point = Vec4([3.0, 3.0, 3.0, 1.0]);
+    assert_eq!(Vec4([7.0, 8.0, 9.0, 1.0]), scale.then(trans) * point);
+    assert_eq!(Vec4([8.0, 10.0, 12.0, 1.0]), trans.then(scale) * point);
+}
+
+#[test]
+fn test_perspective() {
+    let proj = Mat4::perspective(TAU / 4.0, 2.0, 1.0, 10.0);
+    let to_ndc = |x, y, z| {
+        let clip = proj * Vec4([x, y, z, 1.0]);
+        Vec3([clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]])
+    };
+
+    // The camera looks down the negative Z-axis, so the near and far planes map to -1 and 1.
+    assert!(to_ndc(0.0, 0.0, -1.0).approx_eq(Vec3([0.0, 0.0, -1.0]), 1e-5));
+    assert!(to_ndc(0.0, 0.0, -10.0).approx_eq(Vec3([0.0, 0.0, 1.0]), 1e-5));
+
+    // With a 90 degree field of view, the top edge of the near plane is at y = 1, and the right
+    // edge is stretched by the aspect ratio.
+    assert!(to_ndc(0.0, 1.0, -1.0).approx_eq(Vec3([0.0, 1.0, -1.0]), 1e-5));
+    assert!(to_ndc(2.0, 0.0, -1.0).approx_eq(Vec3([1.0, 0.0, -1.0]), 1e-5));
+}
+
+#[test]
+#[should_panic]
+fn test_perspective_zero_aspect() {
+    Mat4::perspective(TAU / 4.0, 0.0, 1.0, 10.0);
+}
+
+#[test]
+#[should_panic]
+fn test_perspective_empty_depth() {
+    Mat4::perspective(TAU / 4.0, 1.0, 1.0, 1.0);
+}
+
+#[test]
+fn test_ortho() {
+    let proj = Mat4::ortho(0.0, 800.0, 0.0, 600.0, 0.0, 8.0);
+
+    assert_eq!(Vec4([-1.0, -1.0, -1.0, 1.0]), proj * Vec4([0.0, 0.0, 0.0, 1.0]));
+    assert_eq!(Vec4([1.0, 1.0, 1.0, 1.0]), proj * Vec4([800.0, 600.0, -8.0, 1.0]));
+    assert_eq!(Vec4([0.0, 0.0, 0.0, 1.0]), proj * Vec4([400.0, 300.0, -4.0, 1.0]));
+}
+
+#[test]
+fn test_normalized() {
+    let v = Vec3([3.0, 0.0, 4.0]);
+    assert_eq!(Vec3([0.6, 0.0, 0.8]), v.normalized());
+    assert_eq!(Vec3([3.0, 0.0, 4.0]), v);
+
+    let mut w = v;
+    w.normalize();
+    assert_eq!(v.normalized(), w);
+
+    // Zero-length vectors come back unchanged instead of full of NaNs.
+    assert_eq!(Vec3::zero(), Vec3::zero().normalized());
+    assert_eq!(Vec4::zero(), Vec4::zero().normalized());
+}
+
+#[test]
+fn test_transpose() {
+    let trans = Mat4::translate(1.0, 2.0, 3.0);
+    let expected = Mat4([
+        [1.0, 0.0, 0.0, 1.0],
+        [0.0, 1.0, 0.0, 2.0],
+        [0.0, 0.0, 1.0, 3.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    assert_eq!(expected, trans.transpose());
+    assert_eq!(trans, trans.transpose().transpose());
+}
+
+#[test]
+fn test_inverse() {
+    let m = Mat4::translate(1.0, 2.0, 3.0) * Mat4::rotate_z(TAU / 8.0) * Mat4::scale(2.0, 3.0, 4.0);
+    assert!((m.determinant() - 24.0).abs() < 1e-4);
+
+    assert!((m * m.inverse().unwrap()).approx_eq(Mat4::identity(), 1e-5));
+
+    assert_eq!(None, Mat4::scale(0.0, 1.0, 1.0).inverse());
+
+    // A small scale has a tiny determinant but is still well-conditioned.
+    let small = Mat4::scale(0.001, 0.001, 0.001);
+    assert!((small * small.inverse().unwrap()).approx_eq(Mat4::identity(), 1e-5));
+}
+
+#[test]
+fn test_scalar_mul() {
+    let a = Vec3([1.0, 2.0, 3.0]);
+    let b = Vec3([3.0, 4.0, 5.0]);
+
+    assert_eq!(Vec3([2.0, 4.0, 6.0]), a * 2.0);
+    assert_eq!(Vec3([2.0, 4.0, 6.0]), 2.0 * a);
+    assert_eq!(Vec3([2.0, 3.0, 4.0]), a * 0.5 + b * 0.5);
+    assert_eq!(Vec4([-1.0, 0.0, 1.0, 2.0]), Vec4([1.0, 0.0, -1.0, -2.0]) * -1.0);
+
+    let trans = Mat4::translate(1.0, 2.0, 3.0);
+    let expected = Mat4([
+        [2.0, 0.0, 0.0, 0.0],
+        [0.0, 2.0, 0.0, 0.0],
+        [0.0, 0.0, 2.0, 0.0],
+        [2.0, 4.0, 6.0, 2.0],
+    ]);
+    assert_eq!(expected, trans * 2.0);
+    assert_eq!(expected, 2.0 * trans);
+    assert_eq!(Mat4::zero(), trans * 0.0);
+}
+
+#[test]
+fn test_quaternion() {
+    let assert_approx_eq = |a: Mat4, b: Mat4| {
+        assert!(a.approx_eq(b, 1e-5), "{:?} != {:?}", a, b);
+    };
+
+    let z_axis = Vec3([0.0, 0.0, 1.0]);
+    assert_approx_eq(Mat4::rotate_z(TAU / 4.0),
+                     Quaternion::from_axis_angle(z_axis, TAU / 4.0).to_mat4());
+    assert_approx_eq(Mat4::rotate_x(1.0),
+                     Quaternion::from_axis_angle(Vec3([2.0, 0.0, 0.0]), 1.0).to_mat4());
+    assert_approx_eq(Mat4::rotate_y(1.0),
+                     Quaternion::from_axis_angle(Vec3([0.0, 1.0, 0.0]), 1.0).to_mat4());
+    assert_approx_eq(Mat4::identity(), Quaternion::identity().to_mat4());
+
+    let a = Quaternion::from_axis_angle(Vec3([1.0, 0.0, 0.0]), TAU / 8.0);
+    let b = Quaternion::from_axis_angle(Vec3([0.0, 1.0, 1.0]), TAU / 3.0);
+    assert_approx_eq(a.to_mat4() * b.to_mat4(), (a * b).to_mat4());
+    assert_approx_eq(b.to_mat4() * a.to_mat4(), (b * a).to_mat4());
+
+    let mut c = Quaternion { w: 2.0, x: 0.0, y: 0.0, z: 0.0 };
+    c.normalize();
+    assert_eq!(Quaternion::identity(), c);
+}
+
+#[test]
+fn test_decompose() {
+    let z_axis = Vec3([0.0, 0.0, 1.0]);
+    let m = Mat4::translate(1.0, 2.0, 3.0) * Mat4::rotate_z(TAU / 6.0) * Mat4::scale(2.0, 3.0, 4.0);
+    let (translation, rotation, scale) = m.decompose();
+
+    assert!(translation.approx_eq(Vec3([1.0, 2.0, 3.0]), 1e-5));
+    assert!(rotation.to_mat4().approx_eq(Mat4::rotate_z(TAU / 6.0), 1e-5));
+    assert!(scale.approx_eq(Vec3([2.0, 3.0, 4.0]), 1e-5));
+
+    let expected = Quaternion::from_axis_angle(z_axis, TAU / 6.0);
+    assert!((rotation.w - expected.w).abs() < 1e-5);
+    assert!((rotation.z - expected.z).abs() < 1e-5);
+
+    // A reflection comes back as a negative X scale.
+    let m = Mat4::rotate_y(1.0) * Mat4::scale(-2.0, 3.0, 4.0);
+    let (translation, rotation, scale) = m.decompose();
+    assert!(translation.approx_eq(Vec3::zero(), 1e-5));
+    assert!(rotation.to_mat4().approx_eq(Mat4::rotate_y(1.0), 1e-5));
+    assert!(scale.approx_eq(Vec3([-2.0, 3.0, 4.0]), 1e-5));
+
+    // Half turns have a zero trace, so they take the other branches of `Quaternion::from_mat4`.
+    for &axis in &[Vec3([1.0, 0.0, 0.0]), Vec3([0.0, 1.0, 0.0]), z_axis] {
+        let m = Quaternion::from_axis_angle(axis, TAU / 2.0).to_mat4();
+        assert!(Quaternion::from_mat4(m).to_mat4().approx_eq(m, 1e-5));
+    }
+}
+
+#[test]
+fn test_lerp() {
+    let a = Vec3([1.0, 2.0, 3.0]);
+    let b = Vec3([3.0, 6.0, -1.0]);
+
+    assert_eq!(a, a.lerp(b, 0.0));
+    assert_eq!(b, a.lerp(b, 1.0));
+    assert_eq!(Vec3([2.0, 4.0, 1.0]), a.lerp(b, 0.5));
+    assert_eq!(Vec3([5.0, 10.0, -5.0]), a.lerp(b, 2.0));
+
+    let c = Vec4([0.0, 0.0, 0.0, 1.0]);
+    let d = Vec4([4.0, -4.0, 2.0, 1.0]);
+
+    assert_eq!(c, c.lerp(d, 0.0));
+    assert_eq!(d, c.lerp(d, 1.0));
+    assert_eq!(Vec4([2.0, -2.0, 1.0, 1.0]), c.lerp(d, 0.5));
+}
+
+#[test]
+fn test_approx_eq() {
+    let full_turn = Mat4::rotate_z(TAU);
+    assert!(full_turn != Mat4::identity());
+    assert!(full_turn.approx_eq(Mat4::identity(), 1e-5));
+    assert!(!Mat4::rotate_z(0.001).approx_eq(Mat4::identity(), 1e-5));
+
+    let v = Vec3([1.0, 2.0, 3.0]);
+    assert!(v.approx_eq(Vec3([1.0, 2.0 + 1e-6, 3.0 - 1e-6]), 1e-5));
+    assert!(!v.approx_eq(Vec3([1.0, 2.0, 3.1]), 1e-5));
+    assert!(Vec4([0.1, 0.2, 0.3, 1.0]).approx_eq(Vec4([0.3 - 0.2, 0.2, 0.3, 1.0]), 1e-5));
+}
+
+#[test]
+fn test_f64() {
+    let scale = Mat4d::scale(2.0, 2.0, 2.0);
+    let trans = Mat4d::translate(1.0, 2.0, 3.0);
+    let combined = trans * scale;
+
+    let original = Vec4d([3.0, 3.0, 3.0, 1.0]);
+    let expected = Vec4d([7.0, 8.0, 9.0, 1.0]);
+    assert_eq!(expected, combined * original);
+
+    let rotation = Mat4d::rotate_z(::std::f64::consts::PI / 3.0);
+    assert!((rotation * rotation.inverse().unwrap()).approx_eq(Mat4d::identity(), 1e-12));
+
+    assert_eq!(Mat4::translate(1.0, 2.0, 3.0) * Mat4::scale(2.0, 2.0, 2.0), Mat4::from(combined));
+    assert_eq!(Vec3d([0.5, 0.25, -1.0]), Vec3d::from(Vec3([0.5, 0.25, -1.0])));
+}
+
+#[test]
+fn test_vec3_vec4_conversions() {
+    let v = Vec3([1.0, 2.0, 3.0]);
+    assert_eq!(Vec4([1.0, 2.0, 3.0, 1.0]), v.to_point());
+    assert_eq!(Vec4([1.0, 2.0, 3.0, 0.0]), v.to_direction());
+    assert_eq!(v.to_point(), Vec4::from(v));
+
+    assert_eq!(v, Vec3::from(v.to_point()));
+    assert_eq!(v, Vec3::from(v.to_direction()));
+    assert_eq!(v, Vec3::from(Vec4([2.0, 4.0, 6.0, 2.0])));
+    assert_eq!(Vec3([-2.0, -4.0, -6.0]), Vec3::from(Vec4([1.0, 2.0, 3.0, -0.5])));
+}
+
+#[test]
+fn test_orthonormal_basis() {
+    let inputs = [
+        Vec3([0.0, 0.0, 1.0]),
+        Vec3([1.0, 0.0, 0.0]),
+        Vec3([0.0, -3.0, 0.0]),
+        Vec3([1.0, 2.0, 3.0]),
+        Vec3([-0.2, 0.1, -5.0]),
+    ];
+
+    for &v in &inputs {
+        let (a, b) = v.orthonormal_basis();
+        let n = v.normalized();
+
+        assert!((a.length() - 1.0).abs() < 1e-5);
+        assert!((b.length() - 1.0).abs() < 1e-5);
+        assert!(a.dot(b).abs() < 1e-5);
+        assert!(a.dot(n).abs() < 1e-5);
+        assert!(b.dot(n).abs() < 1e-5);
+        assert!(n.cross(a).approx_eq(b, 1e-5));
+    }
+}
+
+#[test]
+fn test_look_at_orthonormal() {
+    // An up vector which isn't perpendicular to the view direction must still give an orthonormal
+    // basis.
+    let eye = Vec3([1.0, -2.0, 0.5]);
+    let center = Vec3([0.2, 0.4, -0.3]);
+    let m = Mat4::look_at(eye, center, Vec3([0.3, 0.1, 1.0]));
+
+    // The basis vectors are the rows of the upper-left 3x3.
+    let x = Vec3([m[0][0], m[1][0], m[2][0]]);
+    let y = Vec3([m[0][1], m[1][1], m[2][1]]);
+    let z = Vec3([m[0][2], m[1][2], m[2][2]]);
+
+    for &v in &[x, y, z] {
+        assert!((v.length() - 1.0).abs() < 1e-5);
+    }
+    assert!(x.dot(y).abs() < 1e-5);
+    assert!(y.dot(z).abs() < 1e-5);
+    assert!(z.dot(x).abs() < 1e-5);
+    assert!(x.cross(y).approx_eq(z, 1e-5));
+
+    // The eye ends up at the origin, looking down the negative Z-axis at the center.
+    assert!(m.transform_point(eye).approx_eq(Vec3::zero(), 1e-5));
+    let center_dir = m.transform_point(center).normalized();
+    assert!(center_dir.approx_eq(Vec3([0.0, 0.0, -1.0]), 1e-5));
+}
+
+#[test]
+fn test_transform_point_and_direction() {
+    let m = Mat4::translate(1.0, 2.0, 3.0) * Mat4::scale(2.0, 2.0, 2.0);
+    let v = Vec3([3.0, 0.0, -1.0]);
+
+    assert_eq!(Vec3([7.0, 2.0, 1.0]), m.transform_point(v));
+    assert_eq!(Vec3([6.0, 0.0, -2.0]), m.transform_direction(v));
+
+    // A point on the near plane lands at z = -1 after the perspective divide.
+    let proj = Mat4::perspective(TAU / 4.0, 1.0, 1.0, 10.0);
+    assert!(proj.transform_point(Vec3([0.0, 0.0, -1.0])).approx_eq(Vec3([0.0, 0.0, -1.0]), 1e-5));
+}
+
+#[test]
+fn test_reflect() {
+    let up = Vec3([0.0, 1.0, 0.0]);
+
+    assert_eq!(Vec3([1.0, 1.0, 0.0]), Vec3([1.0, -1.0, 0.0]).reflect(up));
+    assert_eq!(Vec3([0.0, 1.0, 0.0]), Vec3([0.0, -1.0, 0.0]).reflect(up));
+    assert_eq!(Vec3([1.0, 0.0, 0.0]), Vec3([1.0, 0.0, 0.0]).reflect(up));
+}
+
+#[test]
+fn test_refract() {
+    let up = Vec3([0.0, 1.0, 0.0]);
+
+    // Head-on rays pass straight through.
+    let straight_down = Vec3([0.0, -1.0, 0.0]);
+    assert!(straight_down.refract(up, 1.0 / 1.5).unwrap().approx_eq(straight_down, 1e-5));
+
+    // Going from air into glass bends toward the normal.
+    let incident = Vec3([1.0, -1.0, 0.0]).normalized();
+    let refracted = incident.refract(up, 1.0 / 1.5).unwrap();
+    assert!((refracted.length() - 1.0).abs() < 1e-5);
+    assert!(refracted[0] > 0.0 && refracted[0] < incident[0]);
+    assert!((refracted[0] - incident[0] / 1.5).abs() < 1e-5);
+
+    // Going from glass into air at a shallow angle reflects entirely.
+    let grazing = Vec3([1.0, -0.2, 0.0]).normalized();
+    assert_eq!(None, grazing.refract(up, 1.5));
+}
+
+#[test]
+fn test_angle_between() {
+    let x = Vec3([1.0, 0.0, 0.0]);
+    let y = Vec3([0.0, 2.0, 0.0]);
+
+    assert!((x.angle_between(y) - TAU / 4.0).abs() < 1e-6);
+    assert_eq!(0.0, x.angle_between(x * 3.0));
+    assert!((x.angle_between(x * -3.0) - TAU / 2.0).abs() < 1e-6);
+    assert!((Vec3([1.0, 1.0, 0.0]).angle_between(x) - TAU / 8.0).abs() < 1e-6);
+
+    assert_eq!(0.0, x.angle_between(Vec3::zero()));
+    assert_eq!(0.0, Vec3::zero().angle_between(Vec3::zero()));
+}
+
+#[test]
+fn test_signed_angle_around() {
+    let x = Vec3([1.0, 0.0, 0.0]);
+    let y = Vec3([0.0, 1.0, 0.0]);
+    let z = Vec3([0.0, 0.0, 1.0]);
+
+    assert!((x.signed_angle_around(y, z) - TAU / 4.0).abs() < 1e-6);
+    assert!((y.signed_angle_around(x, z) + TAU / 4.0).abs() < 1e-6);
+    assert!((x.signed_angle_around(y, z * -1.0) + TAU / 4.0).abs() < 1e-6);
+    assert_eq!(0.0, x.signed_angle_around(x, z));
+    assert!((x.signed_angle_around(x * -1.0, z).abs() - TAU / 2.0).abs() < 1e-6);
+    assert_eq!(0.0, x.signed_angle_around(Vec3::zero(), z));
+}
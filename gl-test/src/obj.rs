@@ -0,0 +1,169 @@
+//! A loader for the subset of the Wavefront OBJ format needed to draw a textured mesh: vertex
+//! positions (`v`), texture coordinates (`vt`), and faces (`f`). Everything else, such as normals,
+//! groups, and materials, is ignored.
+
+use gl::types::*;
+use renderer::{read_file, Vertex};
+use std::collections::HashMap;
+use std::str::SplitWhitespace;
+
+/// Load the mesh in the OBJ file at `path` as a vertex array and an index buffer of triangles.
+pub fn load_obj(path: &str) -> Result<(Vec<Vertex>, Vec<GLuint>), String> {
+    let source = try!(read_file(path));
+    parse_obj(&source).map_err(|err| format!("Failed to load '{}': {}", path, err))
+}
+
+/// Parse the source of an OBJ file. See `load_obj`.
+fn parse_obj(source: &str) -> Result<(Vec<Vertex>, Vec<GLuint>), String> {
+    let mut positions = Vec::new();
+    let mut texcoords = Vec::new();
+    let mut vertices = Vec::new();
+    let mut elements = Vec::new();
+
+    // Each distinct pair of position and texture coordinate indices becomes one vertex, which
+    // every face corner using that pair shares.
+    let mut vertex_indices: HashMap<(usize, Option<usize>), GLuint> = HashMap::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("v") => {
+                let v = try!(parse_floats(words, 3, line_number));
+                positions.push((v[0], v[1], v[2]));
+            },
+            Some("vt") => {
+                let vt = try!(parse_floats(words, 2, line_number));
+                texcoords.push((vt[0], vt[1]));
+            },
+            Some("f") => {
+                let mut face = Vec::new();
+
+                for corner in words {
+                    let key = try!(parse_face_corner(corner, positions.len(), texcoords.len())
+                        .map_err(|err| format!("line {}: {}", line_number, err)));
+
+                    let index = *vertex_indices.entry(key).or_insert_with(|| {
+                        let (x, y, z) = positions[key.0];
+
+                        // OBJ puts the origin of texture space at the bottom-left, but our
+                        // textures are uploaded with their top row first.
+                        let (s, t) = key.1.map_or((0.0, 0.0), |i| texcoords[i]);
+
+                        vertices.push(Vertex { x: x, y: y, z: z, r: 1.0, g: 1.0, b: 1.0,
+                                               s: s, t: 1.0 - t });
+                        (vertices.len() - 1) as GLuint
+                    });
+
+                    face.push(index);
+                }
+
+                if face.len() < 3 {
+                    return Err(format!("line {}: face has fewer than 3 vertices", line_number));
+                }
+
+                // Triangulate the face as a fan around its first vertex.
+                for j in 1..face.len() - 1 {
+                    elements.extend_from_slice(&[face[0], face[j], face[j + 1]]);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok((vertices, elements))
+}
+
+/// Parse the remaining words of a line as at least `count` floats, returning only the first
+/// `count`. Any extra numbers, such as the optional `w` component on `v` and `vt` lines or the
+/// vertex colors some exporters append to `v` lines, are ignored.
+fn parse_floats(words: SplitWhitespace, count: usize, line_number: usize)
+                -> Result<Vec<f32>, String> {
+    let words: Vec<&str> = words.collect();
+    if words.len() < count {
+        return Err(format!("line {}: expected at least {} numbers, found {}",
+                           line_number, count, words.len()));
+    }
+
+    let mut values: Vec<f32> = try!(words.iter().map(|word| {
+        word.parse().map_err(|_| format!("line {}: invalid number '{}'", line_number, word))
+    }).collect());
+    values.truncate(count);
+    Ok(values)
+}
+
+/// Parse one corner of a face (`v`, `v/vt`, `v//vn`, or `v/vt/vn`) into zero-based position and
+/// texture coordinate indices, given how many of each have been defined so far.
+fn parse_face_corner(corner: &str, num_positions: usize, num_texcoords: usize)
+                     -> Result<(usize, Option<usize>), String> {
+    let mut parts = corner.split('/');
+    let position = try!(parse_index(parts.next().unwrap_or(""), num_positions));
+    let texcoord = match parts.next() {
+        None | Some("") => None,
+        Some(index) => Some(try!(parse_index(index, num_texcoords))),
+    };
+    Ok((position, texcoord))
+}
+
+/// Parse a one-based OBJ index, or a negative index counting back from the most recently defined
+/// element, into a zero-based index into a list of `len` elements.
+fn parse_index(index: &str, len: usize) -> Result<usize, String> {
+    let n: isize = try!(index.parse().map_err(|_| format!("invalid index '{}'", index)));
+
+    let resolved = if n < 0 { len as isize + n } else { n - 1 };
+    if n == 0 || resolved < 0 || resolved >= len as isize {
+        return Err(format!("index {} out of range", n));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[test]
+fn test_parse_obj() {
+    let source = "
+        # A unit square as a single quad, with its last corner given as a negative index.
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 1
+        vn 0 0 1
+        f 1/1/1 2/1/1 3/2/1 -1/2/1
+    ";
+
+    let (vertices, elements) = parse_obj(source).unwrap();
+
+    assert_eq!(vec![
+        Vertex { x: 0.0, y: 0.0, z: 0.0, r: 1.0, g: 1.0, b: 1.0, s: 0.0, t: 1.0 },
+        Vertex { x: 1.0, y: 0.0, z: 0.0, r: 1.0, g: 1.0, b: 1.0, s: 0.0, t: 1.0 },
+        Vertex { x: 1.0, y: 1.0, z: 0.0, r: 1.0, g: 1.0, b: 1.0, s: 1.0, t: 0.0 },
+        Vertex { x: 0.0, y: 1.0, z: 0.0, r: 1.0, g: 1.0, b: 1.0, s: 1.0, t: 0.0 },
+    ], vertices);
+    assert_eq!(vec![0, 1, 2, 0, 2, 3], elements);
+
+    // Faces sharing a corner share its vertex.
+    let (vertices, elements) = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 3 2 1").unwrap();
+    assert_eq!(3, vertices.len());
+    assert_eq!(vec![0, 1, 2, 2, 1, 0], elements);
+
+    // Extra components on `v` and `vt` lines are ignored.
+    let (vertices, _) = parse_obj("v 1 2 3 1
+vt 0.5 0.5 0.0
+f 1/1 1/1 1/1").unwrap();
+    assert_eq!(Vertex { x: 1.0, y: 2.0, z: 3.0, r: 1.0, g: 1.0, b: 1.0, s: 0.5, t: 0.5 },
+               vertices[0]);
+}
+
+#[test]
+fn test_parse_obj_errors() {
+    assert_eq!(Err("line 2: invalid number 'x'".to_string()),
+               parse_obj("v 0 0 0\nv 0 x 0"));
+    assert_eq!(Err("line 1: expected at least 2 numbers, found 1".to_string()),
+               parse_obj("vt 0"));
+    assert_eq!(Err("line 2: index 2 out of range".to_string()),
+               parse_obj("v 0 0 0\nf 1 1 2"));
+    assert_eq!(Err("line 4: face has fewer than 3 vertices".to_string()),
+               parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2"));
+}
@@ -0,0 +1,377 @@
+use gl;
+use gl::types::*;
+use gl_objects::{Buffer, Shader, ShaderProgram, Texture, VertexArray};
+use glfw;
+use imagefmt;
+use math::{Mat4, TAU};
+use std::fs::File;
+use std::io::Read;
+use std::mem;
+use std::ptr;
+
+const VERTEX_SHADER_PATH: &'static str = "shaders/basic.vert";
+const FRAGMENT_SHADER_PATH: &'static str = "shaders/basic.frag";
+
+/// The images to load as textures, paired with the sampler uniforms they're bound to. The texture
+/// at index `i` is placed in texture unit `gl::TEXTURE0 + i`.
+static TEXTURES: [(&'static str, &'static str); 2] = [
+    ("sample.png", "tex_kitten"),
+    ("sample2.png", "tex_puppy"),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C, packed)]
+pub struct Vertex {
+    // Position.
+    pub x: f32, pub y: f32, pub z: f32,
+
+    // Color.
+    pub r: f32, pub g: f32, pub b: f32,
+
+    // Texture.
+    pub s: f32, pub t: f32,
+}
+
+/// The geometry drawn when no model file is given: a colored quad in the XY plane.
+pub static VERTICES: [Vertex; 4] = [
+    Vertex { x: -0.5, y:  0.5, z: 0.0, r: 1.0, g: 0.0, b: 0.0, s: 0.0, t: 0.0 }, // Top-left
+    Vertex { x:  0.5, y:  0.5, z: 0.0, r: 0.0, g: 1.0, b: 0.0, s: 1.0, t: 0.0 }, // Top-right
+    Vertex { x:  0.5, y: -0.5, z: 0.0, r: 0.0, g: 0.0, b: 1.0, s: 1.0, t: 1.0 }, // Bottom-right
+    Vertex { x: -0.5, y: -0.5, z: 0.0, r: 1.0, g: 1.0, b: 1.0, s: 0.0, t: 1.0 }, // Bottom-left
+];
+
+/// Wound counter-clockwise as seen from +Z, so the quad's front faces the camera's starting
+/// position and isn't culled.
+pub static ELEMENTS: [GLuint; 6] = [
+    0, 3, 2, // Bottom-left triangle
+    2, 1, 0, // Top-right triangle
+];
+
+/// Rendering settings which can be adjusted at runtime. The `Default` impl is the state the demo
+/// starts in and returns to when reset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// The factor the final color is multiplied by in the fragment shader.
+    pub exposure: f32,
+
+    /// Whether to draw only the edges of triangles rather than filling them.
+    pub wireframe: bool,
+
+    /// Whether to skip drawing triangles facing away from the camera.
+    pub cull_back_faces: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            exposure: 1.0,
+            wireframe: false,
+            cull_back_faces: false,
+        }
+    }
+}
+
+unsafe fn compile_shader(shader_type: GLenum, source: &str) -> Result<Shader, String> {
+    let shader = Shader::new(shader_type);
+    let source_ptr = source.as_bytes().as_ptr() as *const GLchar;
+    let source_len = source.len() as i32;
+    gl::ShaderSource(shader.id(), 1, &source_ptr, &source_len);
+    gl::CompileShader(shader.id());
+
+    let mut status = gl::FALSE as i32;
+    gl::GetShaderiv(shader.id(), gl::COMPILE_STATUS, &mut status);
+
+    if status == gl::TRUE as i32 {
+        Ok(shader)
+    } else {
+        let mut log_len = 0;
+        gl::GetShaderiv(shader.id(), gl::INFO_LOG_LENGTH, &mut log_len);
+        if log_len == 0 { return Err(String::new()) }
+
+        let mut buf = Vec::with_capacity(log_len as usize);
+        buf.set_len(log_len as usize - 1); // Subtract 1 to ignore the trailing null.
+        gl::GetShaderInfoLog(shader.id(), log_len, ptr::null_mut(),
+                             buf.as_mut_ptr() as *mut GLchar);
+
+        Err(String::from_utf8_lossy(&buf).into_owned())
+    }
+}
+
+unsafe fn link_program(vertex_shader: &Shader, fragment_shader: &Shader)
+                       -> Result<ShaderProgram, String> {
+    let program = ShaderProgram::new();
+    gl::AttachShader(program.id(), vertex_shader.id());
+    gl::AttachShader(program.id(), fragment_shader.id());
+    gl::BindFragDataLocation(program.id(), 0, gl_str!("out_color"));
+    gl::LinkProgram(program.id());
+
+    let mut status = gl::FALSE as i32;
+    gl::GetProgramiv(program.id(), gl::LINK_STATUS, &mut status);
+
+    if status == gl::TRUE as i32 {
+        Ok(program)
+    } else {
+        let mut log_len = 0;
+        gl::GetProgramiv(program.id(), gl::INFO_LOG_LENGTH, &mut log_len);
+        if log_len == 0 { return Err(String::new()) }
+
+        let mut buf = Vec::with_capacity(log_len as usize);
+        buf.set_len(log_len as usize - 1); // Subtract 1 to ignore the trailing null.
+        gl::GetProgramInfoLog(program.id(), log_len, ptr::null_mut(),
+                              buf.as_mut_ptr() as *mut GLchar);
+
+        Err(String::from_utf8_lossy(&buf).into_owned())
+    }
+}
+
+/// A linked shader program along with the locations of the uniforms set each frame.
+struct Program {
+    shader_program: ShaderProgram,
+
+    // The shaders are kept alive as long as the program they're attached to.
+    _vertex_shader: Shader,
+    _fragment_shader: Shader,
+
+    trans_uniform: GLint,
+    time_uniform: GLint,
+    exposure_uniform: GLint,
+}
+
+impl Program {
+    /// Read the shader sources from disk, then compile and link them into a new program.
+    unsafe fn load() -> Result<Program, String> {
+        let vertex_source = try!(read_file(VERTEX_SHADER_PATH));
+        let fragment_source = try!(read_file(FRAGMENT_SHADER_PATH));
+
+        let vertex_shader = try!(compile_shader(gl::VERTEX_SHADER, &vertex_source));
+        let fragment_shader = try!(compile_shader(gl::FRAGMENT_SHADER, &fragment_source));
+
+        // Link the vertex and fragment shaders into a shader program.
+        let shader_program = try!(link_program(&vertex_shader, &fragment_shader));
+        let id = shader_program.id();
+
+        Ok(Program {
+            shader_program: shader_program,
+            _vertex_shader: vertex_shader,
+            _fragment_shader: fragment_shader,
+            trans_uniform: gl::GetUniformLocation(id, gl_str!("trans")),
+            time_uniform: gl::GetUniformLocation(id, gl_str!("time")),
+            exposure_uniform: gl::GetUniformLocation(id, gl_str!("exposure")),
+        })
+    }
+
+    fn id(&self) -> GLuint {
+        self.shader_program.id()
+    }
+
+    /// Make this the current program and point its vertex attributes at the vertex data. The
+    /// vertex array and vertex buffer must already be bound.
+    unsafe fn activate(&self) {
+        gl::UseProgram(self.id());
+
+        // Specify the layout of the vertex data.
+        let position_attrib = gl::GetAttribLocation(self.id(), gl_str!("position"));
+        gl::EnableVertexAttribArray(position_attrib as u32);
+        gl::VertexAttribPointer(position_attrib as u32, 3, gl::FLOAT, gl::FALSE,
+                                mem::size_of::<Vertex>() as i32, ptr::null());
+
+        let position_attrib = gl::GetAttribLocation(self.id(), gl_str!("color"));
+        gl::EnableVertexAttribArray(position_attrib as u32);
+        gl::VertexAttribPointer(position_attrib as u32, 3, gl::FLOAT, gl::FALSE,
+                                mem::size_of::<Vertex>() as i32,
+                                (3 * mem::size_of::<f32>()) as *const ());
+
+        let position_attrib = gl::GetAttribLocation(self.id(), gl_str!("texcoord"));
+        gl::EnableVertexAttribArray(position_attrib as u32);
+        gl::VertexAttribPointer(position_attrib as u32, 2, gl::FLOAT, gl::FALSE,
+                                mem::size_of::<Vertex>() as i32,
+                                (6 * mem::size_of::<f32>()) as *const ());
+    }
+}
+
+pub fn read_file(path: &str) -> Result<String, String> {
+    let mut contents = String::new();
+    try!(File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|err| format!("Failed to read '{}': {}", path, err)));
+    Ok(contents)
+}
+
+/// Create a 2D texture from the image at `path`, bind it to the given texture unit (e.g.
+/// `gl::TEXTURE0`), and point the named sampler uniform of `program` at that unit.
+unsafe fn load_texture(program: GLuint, unit: GLenum, path: &str, uniform: &str)
+                       -> Result<Texture, String> {
+    let image = try!(imagefmt::read(path, imagefmt::ColFmt::RGB)
+        .map_err(|err| format!("Failed to load texture '{}': {:?}", path, err)));
+
+    let texture = Texture::new();
+
+    gl::ActiveTexture(unit);
+    gl::BindTexture(gl::TEXTURE_2D, texture.id());
+    gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, image.w as i32, image.h as i32,
+                   0, gl::RGB, gl::UNSIGNED_BYTE, image.buf.as_ptr() as *const ());
+    bind_sampler(program, unit, uniform);
+
+    gl::GenerateMipmap(gl::TEXTURE_2D);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+
+    Ok(texture)
+}
+
+/// Point the named sampler uniform of `program` at the given texture unit (e.g. `gl::TEXTURE0`).
+/// `program` must be the current program.
+unsafe fn bind_sampler(program: GLuint, unit: GLenum, uniform: &str) {
+    let mut name = uniform.as_bytes().to_vec();
+    name.push(0);
+    gl::Uniform1i(gl::GetUniformLocation(program, name.as_ptr() as *const GLchar),
+                  (unit - gl::TEXTURE0) as i32);
+}
+
+/// Owns the GL objects for the scene and draws it. An OpenGL context must be current for the
+/// whole lifetime of a `Renderer`, including when it's dropped.
+pub struct Renderer {
+    pub settings: Settings,
+    proj: Mat4,
+
+    /// The number of indices in the element buffer.
+    element_count: usize,
+
+    // These are dropped in the order they're declared, the reverse of the order they're created.
+    _textures: Vec<Texture>,
+    program: Program,
+    _ebo: Buffer,
+    _vbo: Buffer,
+    _vao: VertexArray,
+}
+
+impl Renderer {
+    /// Load the OpenGL functions for `window`'s context (which must be current) and set up
+    /// everything needed to draw the scene, uploading the given mesh as its geometry.
+    pub fn new(window: &mut glfw::Window, vertices: &[Vertex], elements: &[GLuint])
+               -> Result<Renderer, String> {
+        // Load OpenGL function pointers.
+        gl::load_with(|symbol| window.get_proc_address(symbol));
+
+        unsafe {
+            // Create a vertex array object.
+            let vao = VertexArray::new();
+            gl::BindVertexArray(vao.id());
+
+            // Create a vertex buffer object and copy the vertex data to it.
+            let vbo = Buffer::new();
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo.id());
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           mem::size_of_val(vertices) as usize,
+                           vertices.as_ptr() as *const (),
+                           gl::STATIC_DRAW);
+
+            // Create an element buffer object and copy the element data to it.
+            let ebo = Buffer::new();
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo.id());
+            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
+                           mem::size_of_val(elements) as usize,
+                           elements.as_ptr() as *const (),
+                           gl::STATIC_DRAW);
+
+            // Only draw the nearest surface where triangles overlap.
+            gl::Enable(gl::DEPTH_TEST);
+
+            // Load the shaders and make the resulting program current.
+            let program = try!(Program::load());
+            program.activate();
+
+            // Create and load textures.
+            let mut textures = Vec::with_capacity(TEXTURES.len());
+            for (i, &(path, uniform)) in TEXTURES.iter().enumerate() {
+                let unit = gl::TEXTURE0 + i as GLenum;
+                textures.push(try!(load_texture(program.id(), unit, path, uniform)));
+            }
+
+            let mut renderer = Renderer {
+                settings: Settings::default(),
+                proj: Mat4::identity(),
+                element_count: elements.len(),
+                _textures: textures,
+                program: program,
+                _ebo: ebo,
+                _vbo: vbo,
+                _vao: vao,
+            };
+
+            // Use the framebuffer size rather than the window size, since they differ on
+            // high-DPI displays.
+            let (width, height) = window.get_framebuffer_size();
+            renderer.resize(width, height);
+
+            Ok(renderer)
+        }
+    }
+
+    /// Update the viewport and projection for a new framebuffer size. The framebuffer is
+    /// zero-sized while the window is minimized, which has no sensible aspect ratio, so in that
+    /// case the old projection is kept until it's restored.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        unsafe { gl::Viewport(0, 0, width, height) };
+        self.proj = Mat4::perspective(TAU / 8.0, width as f32 / height as f32, 1.0, 10.0);
+    }
+
+    /// Reload the shaders from disk and swap them in. If they fail to compile or link, the error
+    /// is returned and the old shaders are left in use.
+    pub fn reload_shaders(&mut self) -> Result<(), String> {
+        unsafe {
+            let program = try!(Program::load());
+            program.activate();
+
+            // The textures are still bound to their units, but the new program's samplers need to
+            // be pointed at them.
+            for (i, &(_, uniform)) in TEXTURES.iter().enumerate() {
+                bind_sampler(program.id(), gl::TEXTURE0 + i as GLenum, uniform);
+            }
+
+            self.program = program;
+            Ok(())
+        }
+    }
+
+    /// Draw a frame as seen through the given view matrix, `elapsed` seconds after the start of
+    /// the animation.
+    pub fn draw(&self, view: Mat4, elapsed: f32) {
+        unsafe {
+            // Update the `time` uniform.
+            gl::Uniform1f(self.program.time_uniform, elapsed);
+
+            // Update the `exposure` uniform in case it was adjusted.
+            gl::Uniform1f(self.program.exposure_uniform, self.settings.exposure);
+
+            // Apply the polygon mode and face culling settings.
+            let mode = if self.settings.wireframe { gl::LINE } else { gl::FILL };
+            gl::PolygonMode(gl::FRONT_AND_BACK, mode);
+            if self.settings.cull_back_faces {
+                gl::Enable(gl::CULL_FACE);
+            } else {
+                gl::Disable(gl::CULL_FACE);
+            }
+
+            // Vary the model matrix over time.
+            let scale = (elapsed * 5.0).sin() * 0.25 + 0.75;
+            let model = Mat4::rotate_z(TAU / 2.0 * elapsed) * Mat4::scale(scale, scale, scale);
+            let trans = self.proj * view * model;
+            gl::UniformMatrix4fv(self.program.trans_uniform, 1, gl::FALSE, &trans[0][0]);
+
+            // Clear the screen to black.
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // Draw the triangles described by the elements array.
+            gl::DrawElements(gl::TRIANGLES, self.element_count as i32, gl::UNSIGNED_INT,
+                             ptr::null());
+        }
+    }
+}